@@ -0,0 +1,213 @@
+use std::env;
+use std::str::FromStr;
+
+use anyhow::Context;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::Address;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use super::retry::{retry_with_backoff, Classified};
+use super::{BalanceBackend, HistoryEntry, Utxo};
+
+/// Speaks the Electrum protocol (as served by electrs/Fulcrum) over a newline-delimited
+/// JSON-RPC connection instead of a REST API, so users can point the scanner at their own
+/// indexer. Configured via `ELECTRUM_HOST` / `ELECTRUM_PORT` / `ELECTRUM_TLS`.
+pub struct ElectrumBackend {
+    max_retries: u32,
+}
+
+impl ElectrumBackend {
+    pub fn new(max_retries: u32) -> Self {
+        Self { max_retries }
+    }
+}
+
+#[async_trait::async_trait]
+impl BalanceBackend for ElectrumBackend {
+    async fn get_address_sats(&self, addr: &Address) -> Result<u32, anyhow::Error> {
+        let scripthash = scripthash(addr);
+        let result = call("blockchain.scripthash.get_balance", json!([scripthash]), self.max_retries).await?;
+        let confirmed = result.get("confirmed").and_then(Value::as_u64).unwrap_or(0);
+        let unconfirmed = result.get("unconfirmed").and_then(Value::as_u64).unwrap_or(0);
+        Ok((confirmed + unconfirmed) as u32)
+    }
+
+    async fn get_address_txcount(&self, addr: &Address) -> Result<u32, anyhow::Error> {
+        let scripthash = scripthash(addr);
+        let result = call("blockchain.scripthash.get_history", json!([scripthash]), self.max_retries).await?;
+        let history = result
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Expected an array from blockchain.scripthash.get_history"))?;
+        Ok(history.len() as u32)
+    }
+
+    async fn get_address_utxos(&self, addr: &Address) -> Result<Vec<Utxo>, anyhow::Error> {
+        let scripthash = scripthash(addr);
+        let result = call("blockchain.scripthash.listunspent", json!([scripthash]), self.max_retries).await?;
+        let entries = result
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Expected an array from blockchain.scripthash.listunspent"))?;
+
+        let tip_height = tip_height(self.max_retries).await?;
+
+        entries
+            .iter()
+            .map(|entry| {
+                let txid = entry
+                    .get("tx_hash")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow::anyhow!("UTXO entry missing 'tx_hash'"))?
+                    .to_string();
+                let vout = entry
+                    .get("tx_pos")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| anyhow::anyhow!("UTXO entry missing 'tx_pos'"))? as u32;
+                let value = entry
+                    .get("value")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| anyhow::anyhow!("UTXO entry missing 'value'"))?;
+                let height = entry.get("height").and_then(Value::as_i64).unwrap_or(0);
+                // A confirmed UTXO is as deep as the gap between the chain tip and its
+                // confirming height, inclusive; an unconfirmed one (height <= 0) has none.
+                let confirmations = if height > 0 { (tip_height + 1).saturating_sub(height as u64) as u32 } else { 0 };
+
+                Ok(Utxo { txid, vout, value, confirmations })
+            })
+            .collect()
+    }
+
+    async fn get_address_history(&self, addr: &Address) -> Result<Vec<HistoryEntry>, anyhow::Error> {
+        let scripthash = scripthash(addr);
+        let result = call("blockchain.scripthash.get_history", json!([scripthash]), self.max_retries).await?;
+        let entries = result
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Expected an array from blockchain.scripthash.get_history"))?;
+
+        entries
+            .iter()
+            .map(|entry| {
+                let txid = entry
+                    .get("tx_hash")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow::anyhow!("History entry missing 'tx_hash'"))?
+                    .to_string();
+                let height = entry.get("height").and_then(Value::as_i64).unwrap_or(0);
+
+                Ok(HistoryEntry { txid, height })
+            })
+            .collect()
+    }
+}
+
+/// Mirrors `rest::display_api_response` for `--query`: dumps the raw Electrum responses
+/// for an address's balance, history and UTXOs as a single pretty-printed JSON object.
+pub async fn display_api_response(addr: String, max_retries: u32) -> Result<(), anyhow::Error> {
+    let addr = Address::from_str(&addr)
+        .context("Failed to parse address")?
+        .assume_checked();
+    let scripthash = scripthash(&addr);
+
+    let balance = call("blockchain.scripthash.get_balance", json!([scripthash]), max_retries).await?;
+    let history = call("blockchain.scripthash.get_history", json!([scripthash]), max_retries).await?;
+    let utxos = call("blockchain.scripthash.listunspent", json!([scripthash]), max_retries).await?;
+
+    let response = json!({ "balance": balance, "history": history, "utxos": utxos });
+    println!("{}", serde_json::to_string_pretty(&response)?);
+    Ok(())
+}
+
+/// Electrum indexes by scripthash: SHA256 of the scriptPubKey, with the 32 output
+/// bytes reversed into little-endian hex.
+fn scripthash(addr: &Address) -> String {
+    let digest = sha256::Hash::hash(addr.script_pubkey().as_bytes());
+    let mut bytes = digest.to_byte_array();
+    bytes.reverse();
+    to_hex(&bytes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+async fn call_once(method: &str, params: &Value) -> Classified<Value> {
+    let host = match env::var("ELECTRUM_HOST") {
+        Ok(host) => host,
+        Err(_) => return Classified::Permanent(anyhow::anyhow!("ELECTRUM_HOST must be set")),
+    };
+    let port: u16 = match env::var("ELECTRUM_PORT").ok().and_then(|p| p.parse().ok()) {
+        Some(port) => port,
+        None => return Classified::Permanent(anyhow::anyhow!("ELECTRUM_PORT must be set to a valid port")),
+    };
+    let tls = env::var("ELECTRUM_TLS").map(|v| v == "true").unwrap_or(false);
+
+    let request_body = json!({
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    let mut request = match serde_json::to_string(&request_body) {
+        Ok(request) => request,
+        Err(err) => return Classified::Permanent(err.into()),
+    };
+    request.push('\n');
+
+    let stream = match TcpStream::connect((host.as_str(), port)).await {
+        Ok(stream) => stream,
+        Err(err) => return Classified::Transient(anyhow::Error::new(err).context("Failed to connect to Electrum server")),
+    };
+
+    let response_line = match send_and_read_line(stream, &host, tls, &request).await {
+        Ok(line) => line,
+        Err(err) => return Classified::Transient(err.context("Electrum request failed")),
+    };
+
+    let response: Value = match serde_json::from_str(&response_line) {
+        Ok(response) => response,
+        Err(err) => return Classified::Permanent(err.into()),
+    };
+
+    if let Some(error) = response.get("error") {
+        if !error.is_null() {
+            return Classified::Permanent(anyhow::anyhow!("Electrum server error: {}", error));
+        }
+    }
+
+    match response.get("result").cloned() {
+        Some(result) => Classified::Ok(result),
+        None => Classified::Permanent(anyhow::anyhow!("Electrum response missing 'result'")),
+    }
+}
+
+async fn call(method: &str, params: Value, max_retries: u32) -> Result<Value, anyhow::Error> {
+    retry_with_backoff(max_retries, || call_once(method, &params)).await
+}
+
+/// Current chain tip height, used to turn a UTXO's confirming height into a confirmation depth.
+async fn tip_height(max_retries: u32) -> Result<u64, anyhow::Error> {
+    let result = call("blockchain.headers.subscribe", json!([]), max_retries).await?;
+    result
+        .get("height")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow::anyhow!("blockchain.headers.subscribe response missing 'height'"))
+}
+
+/// Writes `request` to `stream` (optionally over TLS) and reads back a single
+/// newline-delimited JSON-RPC response line.
+async fn send_and_read_line(stream: TcpStream, host: &str, tls: bool, request: &str) -> Result<String, anyhow::Error> {
+    let mut line = String::new();
+
+    if tls {
+        let connector = tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new()?);
+        let mut tls_stream = connector.connect(host, stream).await?;
+        tls_stream.write_all(request.as_bytes()).await?;
+        BufReader::new(tls_stream).read_line(&mut line).await?;
+    } else {
+        let mut stream = stream;
+        stream.write_all(request.as_bytes()).await?;
+        BufReader::new(stream).read_line(&mut line).await?;
+    }
+
+    Ok(line)
+}
@@ -0,0 +1,67 @@
+use std::env;
+
+use bitcoin::Address;
+use serde::{Deserialize, Serialize};
+
+mod electrum;
+mod rest;
+mod retry;
+
+pub use electrum::ElectrumBackend;
+pub use rest::RestBackend;
+
+/// A single unspent output controlled by a scanned address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Utxo {
+    pub txid: String,
+    pub vout: u32,
+    pub value: u64,
+    pub confirmations: u32,
+}
+
+/// A single transaction an address appears in. `height` is the confirming block height,
+/// or `0`/negative for an unconfirmed transaction (matching the Electrum protocol's convention).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub txid: String,
+    pub height: i64,
+}
+
+/// A source of address balance/activity data. `RestBackend` talks to a generic
+/// block-explorer style HTTP API; `ElectrumBackend` talks to an electrs/Fulcrum
+/// node directly. Selected via `--backend`/`BACKEND`.
+#[async_trait::async_trait]
+pub trait BalanceBackend: Send + Sync {
+    async fn get_address_sats(&self, addr: &Address) -> Result<u32, anyhow::Error>;
+    async fn get_address_txcount(&self, addr: &Address) -> Result<u32, anyhow::Error>;
+    async fn get_address_utxos(&self, addr: &Address) -> Result<Vec<Utxo>, anyhow::Error>;
+    async fn get_address_history(&self, addr: &Address) -> Result<Vec<HistoryEntry>, anyhow::Error>;
+}
+
+/// Resolves `--backend`/`BACKEND` to a backend name, defaulting to `"rest"`.
+fn backend_name(backend: Option<String>) -> String {
+    backend
+        .or_else(|| env::var("BACKEND").ok())
+        .unwrap_or_else(|| "rest".to_string())
+}
+
+/// Picks the backend from `--backend` (falling back to the `BACKEND` env var, then
+/// `"rest"`). Accepts `"rest"` or `"electrum"`. `max_retries` bounds the retry layer
+/// each backend wraps its requests in.
+pub fn select_backend(backend: Option<String>, max_retries: u32) -> Result<Box<dyn BalanceBackend>, anyhow::Error> {
+    match backend_name(backend).to_lowercase().as_str() {
+        "rest" => Ok(Box::new(RestBackend::new(max_retries))),
+        "electrum" => Ok(Box::new(ElectrumBackend::new(max_retries))),
+        name => Err(anyhow::anyhow!("Unknown backend '{}' (expected 'rest' or 'electrum')", name)),
+    }
+}
+
+/// Displays the raw, backend-specific API response for `--query`, honoring the same
+/// `--backend`/`BACKEND` selection as a normal scan.
+pub async fn display_api_response(addr: String, backend: Option<String>, max_retries: u32) -> Result<(), anyhow::Error> {
+    match backend_name(backend).to_lowercase().as_str() {
+        "rest" => rest::display_api_response(addr).await,
+        "electrum" => electrum::display_api_response(addr, max_retries).await,
+        name => Err(anyhow::anyhow!("Unknown backend '{}' (expected 'rest' or 'electrum')", name)),
+    }
+}
@@ -0,0 +1,123 @@
+use std::{collections::HashMap, env};
+use bitcoin::Address;
+use serde_json::Value;
+
+use super::retry::{retry_with_backoff, Classified};
+use super::{BalanceBackend, HistoryEntry, Utxo};
+
+/// The original backend: a generic REST template (`API_ADDRESS_URL_TEMPLATE`) with
+/// JSON paths (`API_ADDRESS_BALANCE_PATH`, `API_ADDRESS_TXCOUNT_PATH`) pointing at the
+/// balance/tx-count fields, so it can be pointed at most block-explorer style APIs.
+pub struct RestBackend {
+    max_retries: u32,
+}
+
+impl RestBackend {
+    pub fn new(max_retries: u32) -> Self {
+        Self { max_retries }
+    }
+}
+
+#[async_trait::async_trait]
+impl BalanceBackend for RestBackend {
+    async fn get_address_sats(&self, addr: &Address) -> Result<u32, anyhow::Error> {
+        get_address_sats(addr.to_string(), self.max_retries).await
+    }
+
+    async fn get_address_txcount(&self, addr: &Address) -> Result<u32, anyhow::Error> {
+        get_address_txcount(addr.to_string(), self.max_retries).await
+    }
+
+    async fn get_address_utxos(&self, addr: &Address) -> Result<Vec<Utxo>, anyhow::Error> {
+        let path = env::var("API_ADDRESS_UTXO_PATH").expect("API_ADDRESS_UTXO_PATH must be set");
+        let map = get_address(addr.to_string(), self.max_retries).await?;
+        get_value_by_path(&map, &path)
+    }
+
+    async fn get_address_history(&self, addr: &Address) -> Result<Vec<HistoryEntry>, anyhow::Error> {
+        let path = env::var("API_ADDRESS_HISTORY_PATH").expect("API_ADDRESS_HISTORY_PATH must be set");
+        let map = get_address(addr.to_string(), self.max_retries).await?;
+        get_value_by_path(&map, &path)
+    }
+}
+
+async fn fetch_address_once(addr: &str) -> Classified<HashMap<String, Value>> {
+    let api_url_template = env::var("API_ADDRESS_URL_TEMPLATE").expect("API_ADDRESS_URL_TEMPLATE must be set");
+    let api_url = api_url_template.replace("{addr}", addr);
+
+    let response = match reqwest::get(api_url).await {
+        Ok(response) => response,
+        Err(err) => return Classified::Transient(anyhow::Error::new(err).context("Failed to fetch data")),
+    };
+
+    let status = response.status();
+    if status.is_server_error() || status.as_u16() == 429 {
+        return Classified::Transient(anyhow::anyhow!("API request failed with status {}", status));
+    }
+    if status.is_client_error() {
+        return Classified::Permanent(anyhow::anyhow!("API request failed with status {}", status));
+    }
+
+    match response.json::<HashMap<String, Value>>().await {
+        Ok(map) => Classified::Ok(map),
+        Err(err) => Classified::Permanent(anyhow::Error::new(err).context("Failed to parse JSON response")),
+    }
+}
+
+async fn get_address(addr: String, max_retries: u32) -> Result<HashMap<String, Value>, anyhow::Error> {
+    retry_with_backoff(max_retries, || fetch_address_once(&addr)).await
+}
+
+pub async fn get_address_sats(addr: String, max_retries: u32) -> Result<u32, anyhow::Error> {
+    let path = env::var("API_ADDRESS_BALANCE_PATH").expect("API_ADDRESS_BALANCE_PATH must bet set");
+    let unit = env::var("API_ADDRESS_BALANCE_UNIT").expect("API_ADDRESS_BALANCE_UNIT must bet set");
+    let map = get_address(addr, max_retries).await?;
+    let bal: u32 = get_value_by_path(&map, &path)?;
+    match unit.as_str() {
+        "btc" => Ok(bal / 100_000_000),
+        "sat" => Ok(bal),
+        _ => Err(anyhow::anyhow!("Unit must be btc or sat")),
+    }
+}
+
+/// Number of transactions an address has been involved in. Unlike balance, this
+/// stays non-zero for an address that received and then fully spent its funds,
+/// so it is the correct signal for gap-limit "used address" discovery.
+pub async fn get_address_txcount(addr: String, max_retries: u32) -> Result<u32, anyhow::Error> {
+    let path = env::var("API_ADDRESS_TXCOUNT_PATH").expect("API_ADDRESS_TXCOUNT_PATH must bet set");
+    let map = get_address(addr, max_retries).await?;
+    let txcount: u32 = get_value_by_path(&map, &path)?;
+    Ok(txcount)
+}
+
+pub async fn display_api_response(addr: String) -> Result<(), anyhow::Error> {
+    let map = get_address(addr, 0).await?;
+    let json = serde_json::to_string_pretty(&map)?;
+    Ok(println!("{}", json))
+}
+
+fn get_value_by_path<T>(map: &HashMap<String, Value>, path: &str) -> Result<T, anyhow::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let keys: Vec<&str> = path.split('.').collect();
+    let mut current_value = Value::Object(map.clone().into_iter().collect());
+
+    for key in keys {
+        match current_value {
+            Value::Object(ref mut obj) => {
+                if let Some(value) = obj.remove(key) {
+                    current_value = value;
+                } else {
+                    return Err(anyhow::anyhow!("Key '{}' not found in JSON path", key));
+                }
+            }
+            _ => return Err(anyhow::anyhow!("Expected an object at key '{}'", key)),
+        }
+    }
+
+    match T::deserialize(current_value) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(anyhow::anyhow!("Failed to deserialize value at path '{}'", path)),
+    }
+}
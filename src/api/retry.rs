@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+const BASE_DELAY_MS: u64 = 200;
+const MAX_DELAY_MS: u64 = 8_000;
+
+/// Distinguishes failures worth retrying (connection errors, HTTP 429/5xx) from
+/// deterministic ones (e.g. a 4xx response) that would just fail again.
+pub enum Classified<T> {
+    Ok(T),
+    Transient(anyhow::Error),
+    Permanent(anyhow::Error),
+}
+
+/// Retries `attempt` up to `max_retries` times on transient failures, with exponential
+/// backoff (doubling from `BASE_DELAY_MS`, capped at `MAX_DELAY_MS`) plus jitter. A
+/// permanent failure, or exhausting the retry budget, returns the last error.
+pub async fn retry_with_backoff<T, Fut>(
+    max_retries: u32,
+    mut attempt: impl FnMut() -> Fut,
+) -> Result<T, anyhow::Error>
+where
+    Fut: std::future::Future<Output = Classified<T>>,
+{
+    let mut delay_ms = BASE_DELAY_MS;
+
+    for attempt_no in 0..=max_retries {
+        match attempt().await {
+            Classified::Ok(value) => return Ok(value),
+            Classified::Permanent(err) => return Err(err),
+            Classified::Transient(err) => {
+                if attempt_no == max_retries {
+                    return Err(err);
+                }
+
+                let jitter = rand::thread_rng().gen_range(0..=delay_ms / 2);
+                tokio::time::sleep(Duration::from_millis(delay_ms + jitter)).await;
+                delay_ms = (delay_ms * 2).min(MAX_DELAY_MS);
+            }
+        }
+    }
+
+    unreachable!("loop always returns on its last iteration")
+}
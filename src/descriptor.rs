@@ -0,0 +1,155 @@
+use std::str::FromStr;
+
+use bitcoin::bip32::{ChildNumber, DerivationPath};
+
+use crate::ScriptPubKeyType;
+
+/// A parsed BIP-380 output descriptor: the script type and derivation info needed to
+/// replace an explicit `--type`/`--path` pair with a single, unambiguous source of truth.
+pub struct ParsedDescriptor {
+    pub script_type: ScriptPubKeyType,
+    pub xpub: String,
+    /// The derivation path up to (but excluding) the `*` wildcard that `main` iterates over.
+    pub base_path: DerivationPath,
+}
+
+/// Parses `wpkh(...)`, `pkh(...)`, `sh(wpkh(...))`, or `tr(...)` descriptors of the form
+/// `wpkh([fingerprint/84h/0h/0h]xpub.../0/*)`, discarding the optional checksum suffix and
+/// key origin info (neither is needed to derive addresses from an already-given xpub).
+pub fn parse(descriptor: &str) -> Result<ParsedDescriptor, anyhow::Error> {
+    let desc = strip_checksum(descriptor.trim());
+
+    let (script_type, key_expr) = if let Some(sh_inner) = unwrap_fn(desc, "sh") {
+        let wpkh_inner = unwrap_fn(sh_inner, "wpkh")
+            .ok_or_else(|| anyhow::anyhow!("Unsupported descriptor: only sh(wpkh(...)) is supported inside sh(...)"))?;
+        (ScriptPubKeyType::P2SHWPKH, wpkh_inner)
+    } else if let Some(inner) = unwrap_fn(desc, "wpkh") {
+        (ScriptPubKeyType::P2WPKH, inner)
+    } else if let Some(inner) = unwrap_fn(desc, "pkh") {
+        (ScriptPubKeyType::P2PKH, inner)
+    } else if let Some(inner) = unwrap_fn(desc, "tr") {
+        (ScriptPubKeyType::P2TR, inner)
+    } else {
+        return Err(anyhow::anyhow!("Unsupported or malformed descriptor: {}", desc));
+    };
+
+    let key_expr = strip_key_origin(key_expr);
+    let mut parts = key_expr.splitn(2, '/');
+    let xpub = parts
+        .next()
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Descriptor is missing an extended public key: {}", descriptor))?
+        .to_string();
+
+    let mut segments: Vec<&str> = match parts.next() {
+        Some(rest) => rest.split('/').collect(),
+        None => vec![],
+    };
+
+    if segments.pop() != Some("*") {
+        return Err(anyhow::anyhow!(
+            "Descriptor must end in a '*' wildcard to drive index iteration: {}",
+            descriptor
+        ));
+    }
+
+    let base_path = if segments.is_empty() {
+        DerivationPath::from(Vec::<ChildNumber>::new())
+    } else {
+        DerivationPath::from_str(&segments.join("/"))?
+    };
+
+    Ok(ParsedDescriptor {
+        script_type,
+        xpub,
+        base_path,
+    })
+}
+
+/// Strips a trailing `#checksum` if present.
+fn strip_checksum(desc: &str) -> &str {
+    match desc.find('#') {
+        Some(pos) => &desc[..pos],
+        None => desc,
+    }
+}
+
+/// If `desc` is `name(inner)`, returns `inner`.
+fn unwrap_fn<'a>(desc: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = format!("{name}(");
+    if desc.starts_with(&prefix) && desc.ends_with(')') {
+        Some(&desc[prefix.len()..desc.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Strips an optional `[fingerprint/path]` key origin prefix.
+fn strip_key_origin(key_expr: &str) -> &str {
+    if let Some(rest) = key_expr.strip_prefix('[') {
+        if let Some(end) = rest.find(']') {
+            return &rest[end + 1..];
+        }
+    }
+    key_expr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XPUB: &str = "xpub6CUGRUonZSQ4TWtTMmzXdrXDtypWKiKrhko4egpiMZbpiaQL2jkwSB1icqYh2cfDfVxdx4df189oLKnC5fSwqPfgyP3hooxujYzAu3fDVmz";
+
+    #[test]
+    fn parses_wpkh_with_key_origin_and_checksum() {
+        let desc = format!("wpkh([d34db33f/84h/0h/0h]{}/0/*)#abcdefgh", XPUB);
+        let parsed = parse(&desc).unwrap();
+        assert_eq!(parsed.script_type, ScriptPubKeyType::P2WPKH);
+        assert_eq!(parsed.xpub, XPUB);
+        assert_eq!(parsed.base_path, DerivationPath::from_str("0").unwrap());
+    }
+
+    #[test]
+    fn parses_pkh_with_no_key_origin() {
+        let desc = format!("pkh({}/1/*)", XPUB);
+        let parsed = parse(&desc).unwrap();
+        assert_eq!(parsed.script_type, ScriptPubKeyType::P2PKH);
+        assert_eq!(parsed.xpub, XPUB);
+        assert_eq!(parsed.base_path, DerivationPath::from_str("1").unwrap());
+    }
+
+    #[test]
+    fn parses_sh_wrapped_wpkh() {
+        let desc = format!("sh(wpkh({}/0/*))", XPUB);
+        let parsed = parse(&desc).unwrap();
+        assert_eq!(parsed.script_type, ScriptPubKeyType::P2SHWPKH);
+        assert_eq!(parsed.xpub, XPUB);
+    }
+
+    #[test]
+    fn rejects_sh_wrapping_anything_other_than_wpkh() {
+        let desc = format!("sh(pkh({}/0/*))", XPUB);
+        assert!(parse(&desc).is_err());
+    }
+
+    #[test]
+    fn parses_tr_with_wildcard_immediately_after_xpub() {
+        let desc = format!("tr({}/*)", XPUB);
+        let parsed = parse(&desc).unwrap();
+        assert_eq!(parsed.script_type, ScriptPubKeyType::P2TR);
+        assert_eq!(parsed.xpub, XPUB);
+        assert_eq!(parsed.base_path, DerivationPath::from(Vec::<ChildNumber>::new()));
+    }
+
+    #[test]
+    fn rejects_descriptor_missing_wildcard() {
+        let desc = format!("wpkh({}/0/0)", XPUB);
+        assert!(parse(&desc).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_function() {
+        let desc = format!("multi(2,{}/0/*)", XPUB);
+        assert!(parse(&desc).is_err());
+    }
+}
@@ -8,8 +8,12 @@ use bitcoin::{
 
 use clap::Parser;
 use dotenv::dotenv;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
 
 mod api;
+mod descriptor;
+mod slip132;
 
 #[derive(Debug, Clone, PartialEq)]
 enum ScriptPubKeyType {
@@ -45,6 +49,241 @@ struct Args {
     /// An address to query (calls the api, displays the response, and exits). This switch is checked first
     #[clap(short, long, required = false)]
     query: Option<String>,
+
+    /// Discover used addresses via BIP-44 gap-limit account discovery instead of scanning a fixed count
+    #[clap(long, required = false)]
+    discover: bool,
+
+    /// Number of consecutive unused addresses that ends discovery for a chain [default: 20]
+    #[clap(long)]
+    gap_limit: Option<u32>,
+
+    /// The balance backend to use: rest or electrum [default: rest]
+    #[clap(long, required = false)]
+    backend: Option<String>,
+
+    /// A BIP-380 output descriptor, e.g. wpkh([fp/84h/0h/0h]xpub.../0/*), in place of the
+    /// xpub + --type + --path combination. Fully determines the script type and derivation path
+    #[clap(long, required = false)]
+    descriptor: Option<String>,
+
+    /// The network to derive addresses for: mainnet, testnet, signet, regtest [default: mainnet,
+    /// or inferred from a tpub/upub/vpub key]
+    #[clap(short, long, required = false)]
+    network: Option<String>,
+
+    /// Number of addresses to fetch balances for concurrently [default: 8]
+    #[clap(long, required = false)]
+    concurrency: Option<usize>,
+
+    /// Max retries for a transient API failure (connection errors, HTTP 429/5xx) [default: 3]
+    #[clap(long, required = false)]
+    max_retries: Option<u32>,
+
+    /// Report each address's UTXOs and aggregate them into a wallet-level spendable set
+    #[clap(long, required = false)]
+    utxos: bool,
+
+    /// Report each address's transaction history
+    #[clap(long, required = false)]
+    history: bool,
+
+    /// Output format: text, json or csv [default: text]
+    #[clap(short = 'o', long, required = false)]
+    output: Option<String>,
+}
+
+/// A single scanned address, flattened for structured (JSON/CSV) output.
+#[derive(Debug, Clone, Serialize)]
+struct ScanResult {
+    address: String,
+    derivation_path: String,
+    script_type: String,
+    index: u32,
+    balance_sats: u32,
+    tx_count: Option<u32>,
+    utxos: Option<Vec<api::Utxo>>,
+    history: Option<Vec<api::HistoryEntry>>,
+}
+
+/// A chain's aggregated balance from `--discover`, the machine-readable form of the
+/// "chain balance" line text mode prints per script type/chain.
+#[derive(Debug, Clone, Serialize)]
+struct ChainSummary {
+    script_type: String,
+    chain: String,
+    balance_sats: u64,
+}
+
+/// The full output of a run, serialized as a whole for `json`/`csv`.
+#[derive(Debug, Clone, Serialize)]
+struct ScanReport {
+    results: Vec<ScanResult>,
+    chain_summaries: Vec<ChainSummary>,
+    wallet_summary: Option<WalletSummary>,
+}
+
+/// Scan-wide settings shared by the fixed-count scan in `main` and `--discover`, kept
+/// together so `discover_accounts` does not grow an argument per flag.
+struct ScanOptions {
+    network: Network,
+    hrp: KnownHrp,
+    include_utxos: bool,
+    include_history: bool,
+    output: OutputFormat,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+fn parse_output(value: &str) -> Result<OutputFormat, anyhow::Error> {
+    match value.to_lowercase().as_str() {
+        "text" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
+        "csv" => Ok(OutputFormat::Csv),
+        _ => Err(anyhow::anyhow!("Unknown output format '{}' (expected text, json or csv)", value)),
+    }
+}
+
+/// Writes `report` to stdout in the requested format. `text` is a no-op: the scan
+/// already printed its results progressively in that mode.
+fn emit_report(report: &ScanReport, format: &OutputFormat) -> Result<(), anyhow::Error> {
+    match format {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(report)?);
+            Ok(())
+        }
+        OutputFormat::Csv => {
+            println!("address,derivation_path,script_type,index,balance_sats,tx_count");
+            for result in &report.results {
+                println!(
+                    "{},{},{},{},{},{}",
+                    csv_field(&result.address),
+                    csv_field(&result.derivation_path),
+                    csv_field(&result.script_type),
+                    result.index,
+                    result.balance_sats,
+                    result.tx_count.map(|c| c.to_string()).unwrap_or_default(),
+                );
+            }
+
+            if !report.chain_summaries.is_empty() {
+                println!();
+                println!("script_type,chain,balance_sats");
+                for chain in &report.chain_summaries {
+                    println!("{},{},{}", csv_field(&chain.script_type), csv_field(&chain.chain), chain.balance_sats);
+                }
+            }
+
+            if report.results.iter().any(|r| r.utxos.is_some()) {
+                println!();
+                println!("address,derivation_path,txid,vout,value_sats,confirmations");
+                for result in &report.results {
+                    for utxo in result.utxos.iter().flatten() {
+                        println!(
+                            "{},{},{},{},{},{}",
+                            csv_field(&result.address),
+                            csv_field(&result.derivation_path),
+                            csv_field(&utxo.txid),
+                            utxo.vout,
+                            utxo.value,
+                            utxo.confirmations,
+                        );
+                    }
+                }
+            }
+
+            if report.results.iter().any(|r| r.history.is_some()) {
+                println!();
+                println!("address,derivation_path,txid,height");
+                for result in &report.results {
+                    for entry in result.history.iter().flatten() {
+                        println!(
+                            "{},{},{},{}",
+                            csv_field(&result.address),
+                            csv_field(&result.derivation_path),
+                            csv_field(&entry.txid),
+                            entry.height,
+                        );
+                    }
+                }
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, quote or newline, doubling any embedded quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Aggregates UTXOs seen across every scanned address into a wallet-level view.
+#[derive(Default, Debug, Clone, Serialize)]
+struct WalletSummary {
+    confirmed_sats: u64,
+    unconfirmed_sats: u64,
+    utxos: Vec<api::Utxo>,
+}
+
+impl WalletSummary {
+    fn add_utxos(&mut self, utxos: Vec<api::Utxo>) {
+        for utxo in &utxos {
+            if utxo.confirmations > 0 {
+                self.confirmed_sats += utxo.value;
+            } else {
+                self.unconfirmed_sats += utxo.value;
+            }
+        }
+        self.utxos.extend(utxos);
+    }
+
+    fn print(&self) {
+        println!("--- Wallet summary ---");
+        println!("Confirmed balance: {} sats", self.confirmed_sats);
+        println!("Unconfirmed balance: {} sats", self.unconfirmed_sats);
+        println!("Spendable UTXOs: {}", self.utxos.len());
+        for utxo in &self.utxos {
+            println!("  {}:{} = {} sats ({} conf)", utxo.txid, utxo.vout, utxo.value, utxo.confirmations);
+        }
+    }
+}
+
+/// Every script type this tool knows how to derive, in the order results are printed.
+const ALL_SCRIPT_TYPES: [ScriptPubKeyType; 4] = [
+    ScriptPubKeyType::P2PKH,
+    ScriptPubKeyType::P2SHWPKH,
+    ScriptPubKeyType::P2WPKH,
+    ScriptPubKeyType::P2TR,
+];
+
+fn parse_network(value: &str) -> Result<Network, anyhow::Error> {
+    match value.to_lowercase().as_str() {
+        "mainnet" | "bitcoin" => Ok(Network::Bitcoin),
+        "testnet" => Ok(Network::Testnet),
+        "signet" => Ok(Network::Signet),
+        "regtest" => Ok(Network::Regtest),
+        _ => Err(anyhow::anyhow!("Unknown network '{}' (expected mainnet, testnet, signet or regtest)", value)),
+    }
+}
+
+/// The HRP a given network's segwit/taproot addresses are encoded with.
+fn hrp_for(network: Network) -> KnownHrp {
+    match network {
+        Network::Bitcoin => KnownHrp::Mainnet,
+        Network::Regtest => KnownHrp::Regtest,
+        _ => KnownHrp::Testnets,
+    }
 }
 
 fn get_value<T>(opt: Option<T>, key: &str, defval: T) -> T
@@ -92,69 +331,314 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let args = Args::parse();
 
+    let max_retries = get_value(args.max_retries, "SCAN_MAX_RETRIES", 3);
+
     if let Some(query) = args.query {
-        api::display_api_response(query).await?;
+        api::display_api_response(query, args.backend.clone(), max_retries).await?;
         return Ok(());
     }
 
-    let val = match args.xpub {
-        Some(v) => v,
-        None => env::var("SCAN_XPUB").expect("SCAN_XPUB must be set when --query is omitted, or passed in as the first positional arg (xpub-scan xpub123abc...)")
+    let secp = Secp256k1::new();
+    let backend = api::select_backend(args.backend, max_retries)?;
+    let network_arg = args.network.clone().or_else(|| env::var("SCAN_NETWORK").ok());
+
+    let from_descriptor = args.descriptor.is_some();
+    let (xpub, path_except_last, mut cn, addr_types, is_testnet) = if let Some(desc) = args.descriptor {
+        let parsed = descriptor::parse(&desc)?;
+        let (xpub, _inferred_type, is_testnet) = slip132::parse_xpub(&parsed.xpub)?;
+        let cn = ChildNumber::from_normal_idx(0)?;
+        (xpub, parsed.base_path, cn, vec![parsed.script_type], is_testnet)
+    } else {
+        let val = match args.xpub {
+            Some(v) => v,
+            None => env::var("SCAN_XPUB").expect("SCAN_XPUB must be set when --query is omitted, or passed in as the first positional arg (xpub-scan xpub123abc...)")
+        };
+
+        let (xpub, inferred_type, is_testnet) = slip132::parse_xpub(&val)?;
+        let path = get_value(args.path, "SCAN_PATH", "0/0".to_string());
+        let deriv_path = DerivationPath::from_str(&path)?;
+        let cn = deriv_path.into_iter().last().unwrap().clone();
+        let path_except_last = get_path_except_last(&deriv_path);
+        let types_vec = get_vec(args.r#type, "SCAN_SCRIPTPUBKEY_TYPE", vec![]);
+        let mut addr_types = parse_enum_values(types_vec)?;
+        if addr_types.is_empty() {
+            if let Some(ty) = inferred_type {
+                addr_types = vec![ty];
+            }
+        }
+        (xpub, path_except_last, cn, addr_types, is_testnet)
     };
 
-    let secp = Secp256k1::new();
-    let xpub = Xpub::from_str(&val)?;
-    let path = get_value(args.path, "SCAN_PATH", "0/0".to_string());
-    let deriv_path = DerivationPath::from_str(&path)?;
-    let mut cn = deriv_path.into_iter().last().unwrap().clone();
-    let path_except_last = get_path_except_last(&deriv_path);
-    let types_vec = get_vec(args.r#type, "SCAN_SCRIPTPUBKEY_TYPE", vec![]);
-    let addr_types = parse_enum_values(types_vec)?;
+    let network = match network_arg {
+        Some(s) => parse_network(&s)?,
+        None if is_testnet => Network::Testnet,
+        None => Network::Bitcoin,
+    };
+    let hrp = hrp_for(network);
+    let output = parse_output(&get_value(args.output.clone(), "SCAN_OUTPUT", "text".to_string()))?;
+    let opts = ScanOptions {
+        network,
+        hrp,
+        include_utxos: args.utxos,
+        include_history: args.history,
+        output,
+    };
+
+    if args.discover {
+        let gap_limit = get_value(args.gap_limit, "SCAN_GAP_LIMIT", 20);
+        // Only a --descriptor's base path pins a specific chain; a bare --path has never
+        // been meaningful for --discover, which otherwise always walks both BIP-44 chains.
+        let discover_base_path = if from_descriptor {
+            path_except_last.clone()
+        } else {
+            DerivationPath::from(Vec::<ChildNumber>::new())
+        };
+        let (results, chain_summaries, summary) =
+            discover_accounts(&secp, &xpub, backend.as_ref(), &addr_types, gap_limit, &discover_base_path, &opts).await?;
+
+        return emit_report(
+            &ScanReport {
+                results,
+                chain_summaries,
+                wallet_summary: if opts.include_utxos { Some(summary) } else { None },
+            },
+            &opts.output,
+        );
+    }
+
     let count = get_value(args.count, "SCAN_COUNT", 10);
     let start: u32 = cn.into();
     let limit = start + count;
+    let concurrency = get_value(args.concurrency, "SCAN_CONCURRENCY", 8);
+    if concurrency == 0 {
+        return Err(anyhow::anyhow!("--concurrency/SCAN_CONCURRENCY must be at least 1"));
+    }
 
     let mut pubkeys = Vec::new();
 
     while limit > cn.into() {
         let dp = path_except_last.child(cn);
-        pubkeys.push(xpub.derive_pub(&secp, &dp)?);
+        pubkeys.push((cn, xpub.derive_pub(&secp, &dp)?));
         cn = cn.increment()?;
     }
 
-    if addr_types.is_empty() || addr_types.contains(&ScriptPubKeyType::P2PKH) {
-        for pk in &pubkeys {
-            let addr = Address::p2pkh(&pk.to_pub(), Network::Bitcoin);
-            let bal = api::get_address_sats(addr.to_string()).await?;
-            println!("{}: {}", addr, bal);
+    let mut summary = WalletSummary::default();
+    let mut scan_results = Vec::new();
+
+    for ty in &ALL_SCRIPT_TYPES {
+        if !(addr_types.is_empty() || addr_types.contains(ty)) {
+            continue;
         }
-    }
 
-    if addr_types.is_empty() || addr_types.contains(&ScriptPubKeyType::P2SHWPKH) {
-        for pk in &pubkeys {
-            let addr = Address::p2shwpkh(&pk.to_pub(), Network::Bitcoin);
-            let bal = api::get_address_sats(addr.to_string()).await?;
-            println!("{}: {}", addr, bal);
+        let mut results: Vec<(usize, ChildNumber, Address, Result<u32, anyhow::Error>)> = stream::iter(pubkeys.iter().enumerate())
+            .map(|(i, (child_cn, pk))| {
+                let addr = derive_address(&secp, pk, ty, opts.network, opts.hrp);
+                let backend = backend.as_ref();
+                let child_cn = *child_cn;
+                async move {
+                    let bal = backend.get_address_sats(&addr).await;
+                    (i, child_cn, addr, bal)
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+
+        results.sort_by_key(|(i, _, _, _)| *i);
+
+        for (_, child_cn, addr, bal) in results {
+            let bal = bal?;
+            if opts.output == OutputFormat::Text {
+                println!("{}: {}", addr, bal);
+            }
+
+            let utxos = if opts.include_utxos {
+                let utxos = backend.get_address_utxos(&addr).await?;
+                if opts.output == OutputFormat::Text {
+                    for utxo in &utxos {
+                        println!("  utxo {}:{} = {} sats ({} conf)", utxo.txid, utxo.vout, utxo.value, utxo.confirmations);
+                    }
+                }
+                summary.add_utxos(utxos.clone());
+                Some(utxos)
+            } else {
+                None
+            };
+
+            let history = if opts.include_history {
+                let history = backend.get_address_history(&addr).await?;
+                if opts.output == OutputFormat::Text {
+                    for entry in &history {
+                        println!("  tx {} (height {})", entry.txid, entry.height);
+                    }
+                }
+                Some(history)
+            } else {
+                None
+            };
+
+            scan_results.push(ScanResult {
+                address: addr.to_string(),
+                derivation_path: path_except_last.child(child_cn).to_string(),
+                script_type: format!("{:?}", ty),
+                index: child_cn.into(),
+                balance_sats: bal,
+                tx_count: None,
+                utxos,
+                history,
+            });
         }
     }
 
-    if addr_types.is_empty() || addr_types.contains(&ScriptPubKeyType::P2WPKH) {
-        for pk in &pubkeys {
-            let addr = Address::p2wpkh(&pk.to_pub(), KnownHrp::Mainnet);
-            let bal = api::get_address_sats(addr.to_string()).await?;
-            println!("{}: {}", addr, bal);
-        }
+    if opts.include_utxos && opts.output == OutputFormat::Text {
+        summary.print();
+    }
+
+    emit_report(
+        &ScanReport {
+            results: scan_results,
+            chain_summaries: vec![],
+            wallet_summary: if opts.include_utxos { Some(summary) } else { None },
+        },
+        &opts.output,
+    )
+}
+
+/// Derives the address for a single chain/index pubkey, for a given script type.
+fn derive_address<C: bitcoin::secp256k1::Verification>(
+    secp: &Secp256k1<C>,
+    pk: &Xpub,
+    ty: &ScriptPubKeyType,
+    network: Network,
+    hrp: KnownHrp,
+) -> Address {
+    match ty {
+        ScriptPubKeyType::P2PKH => Address::p2pkh(&pk.to_pub(), network),
+        ScriptPubKeyType::P2SHWPKH => Address::p2shwpkh(&pk.to_pub(), network),
+        ScriptPubKeyType::P2WPKH => Address::p2wpkh(&pk.to_pub(), hrp),
+        ScriptPubKeyType::P2TR => Address::p2tr(secp, pk.to_x_only_pub(), None, hrp),
     }
+}
+
+/// Standard BIP-44 gap-limit account discovery: for each selected script type and each
+/// chain under `base_path`, derive addresses from index 0 upward until `gap_limit`
+/// consecutive unused addresses are found, then report the used addresses and the
+/// aggregated balance per chain. `base_path` is normally empty, in which case chains
+/// 0 (external) and 1 (change) are both enumerated beneath `xpub`; a non-empty
+/// `base_path` (e.g. from a chain-scoped `--descriptor`) is taken as the single,
+/// already-chosen chain to walk instead.
+async fn discover_accounts<C: bitcoin::secp256k1::Verification>(
+    secp: &Secp256k1<C>,
+    xpub: &Xpub,
+    backend: &dyn api::BalanceBackend,
+    addr_types: &[ScriptPubKeyType],
+    gap_limit: u32,
+    base_path: &DerivationPath,
+    opts: &ScanOptions,
+) -> Result<(Vec<ScanResult>, Vec<ChainSummary>, WalletSummary), anyhow::Error> {
+    let chains: Vec<(DerivationPath, String)> = if base_path.into_iter().next().is_none() {
+        vec![
+            (DerivationPath::from(vec![ChildNumber::from_normal_idx(0)?]), "external".to_string()),
+            (DerivationPath::from(vec![ChildNumber::from_normal_idx(1)?]), "change".to_string()),
+        ]
+    } else {
+        vec![(base_path.clone(), base_path.to_string())]
+    };
+
+    let mut summary = WalletSummary::default();
+    let mut scan_results = Vec::new();
+    let mut chain_summaries = Vec::new();
+    let text = opts.output == OutputFormat::Text;
 
-    if addr_types.is_empty() || addr_types.contains(&ScriptPubKeyType::P2TR) {
-        for pk in &pubkeys {
-            let addr = Address::p2tr(&secp, pk.to_x_only_pub(), None, KnownHrp::Mainnet);
-            let bal = api::get_address_sats(addr.to_string()).await?;
-            println!("{}: {}", addr, bal);
+    for ty in &ALL_SCRIPT_TYPES {
+        if !(addr_types.is_empty() || addr_types.contains(ty)) {
+            continue;
         }
+
+        for (chain_path, chain_name) in &chains {
+            let mut unused_run = 0u32;
+            let mut idx = 0u32;
+            let mut chain_balance: u64 = 0;
+
+            if text {
+                println!("{:?} {} chain:", ty, chain_name);
+            }
+
+            loop {
+                let dp = chain_path.child(ChildNumber::from_normal_idx(idx)?);
+                let child = xpub.derive_pub(secp, &dp)?;
+                let addr = derive_address(secp, &child, ty, opts.network, opts.hrp);
+
+                let txcount = backend.get_address_txcount(&addr).await?;
+                if txcount > 0 {
+                    unused_run = 0;
+                    let bal = backend.get_address_sats(&addr).await?;
+                    chain_balance += bal as u64;
+                    if text {
+                        println!("  {}/{}: {} ({})", chain_name, idx, addr, bal);
+                    }
+
+                    let utxos = if opts.include_utxos {
+                        let utxos = backend.get_address_utxos(&addr).await?;
+                        if text {
+                            for utxo in &utxos {
+                                println!("    utxo {}:{} = {} sats ({} conf)", utxo.txid, utxo.vout, utxo.value, utxo.confirmations);
+                            }
+                        }
+                        summary.add_utxos(utxos.clone());
+                        Some(utxos)
+                    } else {
+                        None
+                    };
+
+                    let history = if opts.include_history {
+                        let history = backend.get_address_history(&addr).await?;
+                        if text {
+                            for entry in &history {
+                                println!("    tx {} (height {})", entry.txid, entry.height);
+                            }
+                        }
+                        Some(history)
+                    } else {
+                        None
+                    };
+
+                    scan_results.push(ScanResult {
+                        address: addr.to_string(),
+                        derivation_path: dp.to_string(),
+                        script_type: format!("{:?}", ty),
+                        index: idx,
+                        balance_sats: bal,
+                        tx_count: Some(txcount),
+                        utxos,
+                        history,
+                    });
+                } else {
+                    unused_run += 1;
+                    if unused_run >= gap_limit {
+                        break;
+                    }
+                }
+
+                idx += 1;
+            }
+
+            if text {
+                println!("  {:?} {} chain balance: {}", ty, chain_name, chain_balance);
+            }
+            chain_summaries.push(ChainSummary {
+                script_type: format!("{:?}", ty),
+                chain: chain_name.clone(),
+                balance_sats: chain_balance,
+            });
+        }
+    }
+
+    if opts.include_utxos && text {
+        summary.print();
     }
 
-    Ok(())
+    Ok((scan_results, chain_summaries, summary))
 }
 
 fn parse_enum_values(values: Vec<String>) -> Result<Vec<ScriptPubKeyType>, anyhow::Error> {
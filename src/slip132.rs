@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use bitcoin::bip32::Xpub;
+
+use crate::ScriptPubKeyType;
+
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xB2, 0x1E];
+const YPUB_VERSION: [u8; 4] = [0x04, 0x9D, 0x7C, 0xB2];
+const ZPUB_VERSION: [u8; 4] = [0x04, 0xB2, 0x47, 0x46];
+const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xCF];
+const UPUB_VERSION: [u8; 4] = [0x04, 0x4A, 0x52, 0x62];
+const VPUB_VERSION: [u8; 4] = [0x04, 0x5F, 0x1C, 0xF6];
+
+/// Parses an xpub/ypub/zpub/tpub/upub/vpub string into an `Xpub`, rewriting SLIP-132
+/// version bytes to the plain `xpub`/`tpub` ones `Xpub::from_str` understands. Returns
+/// the script type the SLIP-132 prefix implies (`None` for plain xpub/tpub, which carry
+/// no type information) and whether the prefix marked the key as testnet.
+pub fn parse_xpub(key: &str) -> Result<(Xpub, Option<ScriptPubKeyType>, bool), anyhow::Error> {
+    let decoded = bitcoin::base58::decode_check(key).context("Failed to base58check-decode extended public key")?;
+    if decoded.len() < 4 {
+        return Err(anyhow::anyhow!("Extended public key is too short"));
+    }
+
+    let version: [u8; 4] = decoded[0..4].try_into().unwrap();
+    let (canonical_version, script_type, is_testnet) = match version {
+        XPUB_VERSION => (XPUB_VERSION, None, false),
+        YPUB_VERSION => (XPUB_VERSION, Some(ScriptPubKeyType::P2SHWPKH), false),
+        ZPUB_VERSION => (XPUB_VERSION, Some(ScriptPubKeyType::P2WPKH), false),
+        TPUB_VERSION => (TPUB_VERSION, None, true),
+        UPUB_VERSION => (TPUB_VERSION, Some(ScriptPubKeyType::P2SHWPKH), true),
+        VPUB_VERSION => (TPUB_VERSION, Some(ScriptPubKeyType::P2WPKH), true),
+        _ => return Err(anyhow::anyhow!("Unrecognized extended public key version bytes")),
+    };
+
+    let mut rewritten = decoded;
+    rewritten[0..4].copy_from_slice(&canonical_version);
+    let xpub_str = bitcoin::base58::encode_check(&rewritten);
+
+    Ok((Xpub::from_str(&xpub_str)?, script_type, is_testnet))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XPUB: &str = "xpub661MyMwAqRbcFcWrnTvEQKkksZZBwaDtFSakLHzsQ9RQVH3HEMeqWwwrYEBJnk6WfCmgXQQ8EiQzjRS87PHWc984bfKnaomNiTcmVeERGKT";
+    const YPUB: &str = "ypub6QqdH2c5z7966uhycphrcQrG3XhdtCDPAZ6y7gtkn9oHYNrWV1pQ91bzZS8tnekS4qtVGszghNmYci3gq5hXQNofU12DAiarzBgQtBhbxW5";
+    const ZPUB: &str = "zpub6jftahH18ngZxCu6TBVUpVwmDVr5ppCt5fdBu5neAABAbUfjjfyxm5G8ae6UnZQMUV1J2MbFA386VzfFYn7YCcVGLLidkdQMFuk4GqsioW6";
+    const TPUB: &str = "tpubD6NzVbkrYhZ4XQhiEeuKcF68Cgeqvxiwo5B3gr41k4BJEZhzJaVvfPcgnGG6KF3kT7Jb8yuzmpF3RbvjWF8kVaaMfG56GLRcJRDBFe5VM2t";
+    const UPUB: &str = "upub57Wa4MvRPNyAhiwWHPZMn4UFMf7r7iFPW725z7KDG8HmKybbUPA9ekySUcJYo28kSHRGGycSrjMM5ZbRxJ3UDS5FzeEWq5JuuHRqKy58Ma4";
+    const VPUB: &str = "vpub5SLqN2bLY4WeZ28d7kLyz9ZkXdGJ4LEtRDYJmWD6e8feP5Qpj3KiGpdaVpG8nvnfqvY52TD1KPhtxrCzfzTV1fkrryvwQz8QB1VUiXQt6iV";
+
+    #[test]
+    fn parses_plain_xpub_as_untyped_mainnet() {
+        let (_, script_type, is_testnet) = parse_xpub(XPUB).unwrap();
+        assert_eq!(script_type, None);
+        assert!(!is_testnet);
+    }
+
+    #[test]
+    fn parses_plain_tpub_as_untyped_testnet() {
+        let (_, script_type, is_testnet) = parse_xpub(TPUB).unwrap();
+        assert_eq!(script_type, None);
+        assert!(is_testnet);
+    }
+
+    #[test]
+    fn parses_ypub_as_p2shwpkh_mainnet() {
+        let (_, script_type, is_testnet) = parse_xpub(YPUB).unwrap();
+        assert_eq!(script_type, Some(ScriptPubKeyType::P2SHWPKH));
+        assert!(!is_testnet);
+    }
+
+    #[test]
+    fn parses_zpub_as_p2wpkh_mainnet() {
+        let (_, script_type, is_testnet) = parse_xpub(ZPUB).unwrap();
+        assert_eq!(script_type, Some(ScriptPubKeyType::P2WPKH));
+        assert!(!is_testnet);
+    }
+
+    #[test]
+    fn parses_upub_as_p2shwpkh_testnet() {
+        let (_, script_type, is_testnet) = parse_xpub(UPUB).unwrap();
+        assert_eq!(script_type, Some(ScriptPubKeyType::P2SHWPKH));
+        assert!(is_testnet);
+    }
+
+    #[test]
+    fn parses_vpub_as_p2wpkh_testnet() {
+        let (_, script_type, is_testnet) = parse_xpub(VPUB).unwrap();
+        assert_eq!(script_type, Some(ScriptPubKeyType::P2WPKH));
+        assert!(is_testnet);
+    }
+
+    #[test]
+    fn rejects_garbage_input() {
+        assert!(parse_xpub("not-an-extended-key").is_err());
+    }
+}